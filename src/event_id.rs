@@ -0,0 +1,39 @@
+//! Event identifiers for custom events emitted by the SDL2 back-end.
+
+use input::event_id::EventId;
+
+/// Event id for controller connected and disconnected events.
+///
+/// The event carries `ControllerDeviceArgs`.
+pub const CONTROLLER_DEVICE: EventId = EventId("sdl2/controller_device");
+
+/// Event id for controllers that were connected but could not be opened.
+///
+/// The event carries `ControllerErrorArgs`.
+pub const CONTROLLER_ERROR: EventId = EventId("sdl2/controller_error");
+
+/// Event id for repeated key presses while a key is held.
+///
+/// The event carries `ButtonArgs`, see `Sdl2Window::set_key_repeat`.
+pub const KEY_REPEAT: EventId = EventId("sdl2/key_repeat");
+
+/// Event id for drag and drop events.
+///
+/// The event carries `DropArgs`.
+pub const DROP: EventId = EventId("sdl2/drop");
+
+/// Event id for clipboard changed events.
+///
+/// The event carries `()`.
+pub const CLIPBOARD_UPDATE: EventId = EventId("sdl2/clipboard_update");
+
+/// Event id for IME text composition events.
+///
+/// The event carries `TextEditingArgs`.
+pub const TEXT_EDITING: EventId = EventId("sdl2/text_editing");
+
+/// Event id for window events without a piston-input equivalent,
+/// such as the window being moved or minimized.
+///
+/// The event carries `WindowEventArgs`.
+pub const WINDOW: EventId = EventId("sdl2/window");
@@ -12,19 +12,49 @@ use window::{BuildFromWindowSettings, OpenGLWindow, ProcAddress, Window, Advance
              WindowSettings, Size, Position, Api, UnsupportedGraphicsApiError};
 use input::{keyboard, Button, ButtonArgs, ButtonState, MouseButton, Input, Motion, CloseArgs,
             ControllerAxisArgs, ControllerButton, Touch, TouchArgs, ControllerHat, TimeStamp,
-            ResizeArgs, Event};
+            ResizeArgs, Event, FileDrag};
 use input::HatState as PistonHat;
 use sdl2::joystick::HatState;
 
 use std::vec::Vec;
+use std::collections::{HashMap, VecDeque};
 use std::time::Duration;
 use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 pub use shader_version::OpenGL;
 
+/// The name of the OpenGL ES graphics API.
+pub const OPENGL_ES: &str = "OpenGL ES";
+
+/// Creates an OpenGL ES version, to be used as graphics API in `WindowSettings`.
+///
+/// SDL supports OpenGL ES 2.0 and later.
+pub fn opengl_es(major: u32, minor: u32) -> Api {
+    Api {
+        api: OPENGL_ES.into(),
+        major,
+        minor,
+    }
+}
+
+pub mod event_id;
+
 struct JoystickState {
+    // All opened devices, including those opened as game controllers.
     joysticks: Vec<sdl2::joystick::Joystick>,
     subsystem: sdl2::JoystickSubsystem,
+    // Devices also opened through the game controller API.
+    controllers: Vec<sdl2::controller::GameController>,
+    // Set when devices with a known mapping should be opened as game controllers.
+    controller_subsystem: Option<sdl2::GameControllerSubsystem>,
+    // Last known state of each hat, by instance id and hat index.
+    hats: HashMap<(u32, u8), PistonHat>,
+    // Used as rumble fallback for devices without native rumble support.
+    haptic_subsystem: Option<sdl2::HapticSubsystem>,
+    // Haptic devices opened so far, by instance id.
+    haptics: HashMap<u32, RumbleHaptic>,
 }
 
 impl JoystickState {
@@ -32,7 +62,886 @@ impl JoystickState {
         JoystickState {
             joysticks: Vec::new(),
             subsystem: subsystem,
+            controllers: Vec::new(),
+            controller_subsystem: None,
+            hats: HashMap::new(),
+            haptic_subsystem: None,
+            haptics: HashMap::new(),
+        }
+    }
+
+    /// Opens all available devices, returning the number of devices.
+    fn open_all(&mut self) -> Result<u32, String> {
+        self.joysticks.clear();
+        self.controllers.clear();
+        self.hats.clear();
+        self.haptics.clear();
+        let available = self.subsystem.num_joysticks()?;
+        for index in 0..available {
+            self.open(index)?;
+        }
+        Ok(available)
+    }
+
+    /// Opens the device at `index`, as a game controller if a mapping is known.
+    /// Returns the instance id, or `None` if the device was already opened.
+    fn open(&mut self, index: u32) -> Result<Option<u32>, String> {
+        let joystick = self.subsystem.open(index).map_err(|e| format!("{}", e))?;
+        let id = joystick.instance_id();
+        if self.is_opened(id) {
+            return Ok(None);
+        }
+        if let Some(ref controller_subsystem) = self.controller_subsystem {
+            if controller_subsystem.is_game_controller(index) {
+                let controller = controller_subsystem.open(index)
+                    .map_err(|e| format!("{}", e))?;
+                self.controllers.push(controller);
+            }
         }
+        // Game controllers keep the joystick handle too, to query device information.
+        self.joysticks.push(joystick);
+        Ok(Some(id))
+    }
+
+    /// Closes the device with instance id `id`.
+    /// Returns `false` if the device was not opened.
+    fn close(&mut self, id: u32) -> bool {
+        let opened = self.is_opened(id);
+        self.joysticks.retain(|j| j.instance_id() != id);
+        self.controllers.retain(|c| c.instance_id() != id);
+        self.hats.retain(|&(which, _), _| which != id);
+        self.haptics.remove(&id);
+        opened
+    }
+
+    fn info(&self, joystick: &sdl2::joystick::Joystick) -> ControllerInfo {
+        use sdl2::joystick::PowerLevel;
+
+        let id = joystick.instance_id();
+        let controller = self.controllers.iter().find(|c| c.instance_id() == id);
+        // Not exposed by the sdl2 crate, looked up through the instance id instead.
+        let player_index = unsafe {
+            sdl2::sys::SDL_JoystickGetPlayerIndex(
+                sdl2::sys::SDL_JoystickFromInstanceID(id as i32))
+        };
+        ControllerInfo {
+            id,
+            name: match controller {
+                Some(c) => c.name(),
+                None => joystick.name(),
+            },
+            guid: joystick.guid().string(),
+            game_controller: controller.is_some(),
+            axes: joystick.num_axes(),
+            buttons: joystick.num_buttons(),
+            hats: joystick.num_hats(),
+            power_level: joystick.power_level().unwrap_or(PowerLevel::Unknown),
+            player_index: if player_index < 0 { None } else { Some(player_index as u32) },
+        }
+    }
+
+    fn rumble(&mut self, id: u32, low_freq: u16, high_freq: u16, duration_ms: u32)
+              -> Result<(), String> {
+        let result = if let Some(c) = self.controllers.iter_mut().find(|c| c.instance_id() == id) {
+            c.set_rumble(low_freq, high_freq, duration_ms)
+        } else if let Some(j) = self.joysticks.iter_mut().find(|j| j.instance_id() == id) {
+            j.set_rumble(low_freq, high_freq, duration_ms)
+        } else {
+            return Err(format!("No controller with id {}", id));
+        };
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                // Fall back to the haptic subsystem, which only has one strength.
+                let strength = low_freq.max(high_freq) as f32 / u16::MAX as f32;
+                match self.haptic(id) {
+                    Some(haptic) => {
+                        if strength > 0.0 {
+                            haptic.play(strength, duration_ms)
+                        } else {
+                            haptic.stop()
+                        }
+                    }
+                    None => Err(format!("{}", e)),
+                }
+            }
+        }
+    }
+
+    fn rumble_triggers(&mut self, id: u32, left: u16, right: u16, duration_ms: u32)
+                       -> Result<(), String> {
+        if let Some(c) = self.controllers.iter_mut().find(|c| c.instance_id() == id) {
+            c.set_rumble_triggers(left, right, duration_ms).map_err(|e| format!("{}", e))
+        } else if let Some(j) = self.joysticks.iter_mut().find(|j| j.instance_id() == id) {
+            j.set_rumble_triggers(left, right, duration_ms).map_err(|e| format!("{}", e))
+        } else {
+            Err(format!("No controller with id {}", id))
+        }
+    }
+
+    /// Returns the haptic device of the device with instance id `id`,
+    /// opening it on first use. Devices without rumble support are not opened.
+    fn haptic(&mut self, id: u32) -> Option<&mut RumbleHaptic> {
+        if !self.haptics.contains_key(&id) {
+            if !self.is_opened(id) {
+                return None;
+            }
+            let haptic_subsystem = self.haptic_subsystem.as_ref()?;
+            let haptic = RumbleHaptic::open(haptic_subsystem, id).ok()?;
+            self.haptics.insert(id, haptic);
+        }
+        self.haptics.get_mut(&id)
+    }
+
+    /// Returns the normalized position of an axis,
+    /// using the identifiers in `controller_axis` for game controllers.
+    fn axis(&self, id: u32, axis: u8) -> Option<f64> {
+        use sdl2::controller::Axis;
+
+        let value = if let Some(c) = self.controllers.iter().find(|c| c.instance_id() == id) {
+            let axis = match axis {
+                controller_axis::LEFT_X => Axis::LeftX,
+                controller_axis::LEFT_Y => Axis::LeftY,
+                controller_axis::RIGHT_X => Axis::RightX,
+                controller_axis::RIGHT_Y => Axis::RightY,
+                controller_axis::TRIGGER_LEFT => Axis::TriggerLeft,
+                controller_axis::TRIGGER_RIGHT => Axis::TriggerRight,
+                _ => return None,
+            };
+            c.axis(axis)
+        } else {
+            let joystick = self.joysticks.iter().find(|j| j.instance_id() == id)?;
+            joystick.axis(axis as u32).ok()?
+        };
+        Some(value as f64 / i16::MAX as f64)
+    }
+
+    fn is_opened(&self, id: u32) -> bool {
+        self.joysticks.iter().any(|j| j.instance_id() == id)
+    }
+
+    fn is_game_controller(&self, id: u32) -> bool {
+        self.controllers.iter().any(|c| c.instance_id() == id)
+    }
+}
+
+// A haptic device opened for simple rumble playback.
+// The sdl2 crate ignores rumble errors, so the device is used through `sdl2::sys`.
+struct RumbleHaptic {
+    raw: *mut sdl2::sys::SDL_Haptic,
+    // Keeps the haptic subsystem alive until the device is closed.
+    _subsystem: sdl2::HapticSubsystem,
+}
+
+impl RumbleHaptic {
+    /// Opens the haptic device of the opened joystick with instance id `id`.
+    fn open(subsystem: &sdl2::HapticSubsystem, id: u32) -> Result<Self, String> {
+        use sdl2::sys;
+
+        let raw = unsafe {
+            sys::SDL_HapticOpenFromJoystick(sys::SDL_JoystickFromInstanceID(id as i32))
+        };
+        if raw.is_null() {
+            return Err(sdl2::get_error());
+        }
+        // Closed on drop if rumble is not available.
+        let haptic = RumbleHaptic { raw, _subsystem: subsystem.clone() };
+        match unsafe { sys::SDL_HapticRumbleSupported(raw) } {
+            1 => {}
+            0 => return Err(format!("Controller {} does not support rumble", id)),
+            _ => return Err(sdl2::get_error()),
+        }
+        if unsafe { sys::SDL_HapticRumbleInit(raw) } != 0 {
+            return Err(sdl2::get_error());
+        }
+        Ok(haptic)
+    }
+
+    fn play(&mut self, strength: f32, duration_ms: u32) -> Result<(), String> {
+        if unsafe { sdl2::sys::SDL_HapticRumblePlay(self.raw, strength, duration_ms) } != 0 {
+            return Err(sdl2::get_error());
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        if unsafe { sdl2::sys::SDL_HapticRumbleStop(self.raw) } != 0 {
+            return Err(sdl2::get_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RumbleHaptic {
+    fn drop(&mut self) {
+        unsafe { sdl2::sys::SDL_HapticClose(self.raw) }
+    }
+}
+
+/// Components of a controller connected or disconnected event.
+///
+/// Emitted as a custom event with id `event_id::CONTROLLER_DEVICE`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct ControllerDeviceArgs {
+    /// The instance id of the controller, as used in `ControllerButton`
+    /// and `ControllerAxisArgs`.
+    pub id: u32,
+    /// Whether the controller was connected or disconnected.
+    pub connected: bool,
+}
+
+/// Components of a controller error event, sent when a connected controller
+/// could not be opened. No connected event is sent for such controllers.
+///
+/// Emitted as a custom event with id `event_id::CONTROLLER_ERROR`.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct ControllerErrorArgs {
+    /// The device index of the controller.
+    /// This is not an instance id, since the controller was not opened.
+    pub device_index: u32,
+    /// The error reported by SDL.
+    pub error: String,
+}
+
+/// Information about an opened controller.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ControllerInfo {
+    /// The instance id of the controller, as used in `ControllerButton`
+    /// and `ControllerAxisArgs`.
+    pub id: u32,
+    /// The name of the device.
+    pub name: String,
+    /// The GUID of the device, which is the same across sessions
+    /// for the same kind of device.
+    pub guid: String,
+    /// Whether the device is opened as a game controller.
+    pub game_controller: bool,
+    /// The number of axes of the underlying joystick.
+    pub axes: u32,
+    /// The number of buttons of the underlying joystick.
+    pub buttons: u32,
+    /// The number of hats of the underlying joystick.
+    pub hats: u32,
+    /// The battery level, or whether the device is wired.
+    pub power_level: sdl2::joystick::PowerLevel,
+    /// The player index assigned to the device, if any.
+    pub player_index: Option<u32>,
+}
+
+/// Components of a drag and drop event.
+///
+/// Emitted as a custom event with id `event_id::DROP`.
+/// Dropping emits `DropState::Begin`, then one event per dropped item,
+/// then `DropState::Complete`. Dropped files are also emitted as
+/// `Input::FileDrag(FileDrag::Drop(path))`, followed by `DropState::File`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DropArgs {
+    /// The drop state.
+    pub state: DropState,
+    /// The mouse position in window coordinates,
+    /// or `None` if the mouse is outside the window.
+    pub position: Option<[f64; 2]>,
+}
+
+/// The state of a drag and drop event.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DropState {
+    /// Dropping items started.
+    Begin,
+    /// A file was dropped.
+    File(PathBuf),
+    /// Text was dropped.
+    Text(String),
+    /// Dropping items completed.
+    Complete,
+}
+
+/// Components of a text editing event, sent while composing text with an IME.
+///
+/// Emitted as a custom event with id `event_id::TEXT_EDITING`.
+/// The composed text is sent as `Input::Text` when committed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TextEditingArgs {
+    /// The text being composed, replacing previous composition text.
+    pub text: String,
+    /// The cursor position in characters.
+    pub cursor: i32,
+    /// The number of characters selected from the cursor.
+    pub selection_length: i32,
+}
+
+/// Window events that have no piston-input equivalent.
+///
+/// Emitted as a custom event with id `event_id::WINDOW`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WindowEventArgs {
+    /// The window was shown.
+    Shown,
+    /// The window was hidden.
+    Hidden,
+    /// The window was exposed and should be redrawn.
+    Exposed,
+    /// The window was moved to a new position.
+    Moved(Position),
+    /// The window size changed, either by the user, the system or the API.
+    /// Changes by the user are also emitted as `Input::Resize`.
+    SizeChanged(Size),
+    /// The window was minimized.
+    Minimized,
+    /// The window was maximized.
+    Maximized,
+    /// The window was restored to normal size and position.
+    Restored,
+    /// The window manager requests the window to close.
+    /// When this is the last window, `Input::Close` follows.
+    Close,
+    /// The window is offered focus.
+    TakeFocus,
+}
+
+/// Fullscreen modes of a window.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FullscreenMode {
+    /// A normal window.
+    Windowed,
+    /// A borderless window covering the display, keeping the desktop display mode.
+    Desktop,
+    /// Exclusive fullscreen, changing the display to a display mode.
+    /// With `None`, the display mode closest to the window size is used.
+    Exclusive(Option<sdl2::video::DisplayMode>),
+}
+
+/// What happens to pending OpenGL commands when a context is released.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ContextReleaseBehavior {
+    /// Pending commands are flushed.
+    Flush,
+    /// Pending commands are left alone, which avoids the cost of flushing
+    /// when switching between contexts.
+    None,
+}
+
+/// Information about a display.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DisplayInfo {
+    /// The display index.
+    pub index: i32,
+    /// The name of the display.
+    pub name: String,
+    /// The bounds of the display in desktop coordinates.
+    pub bounds: sdl2::rect::Rect,
+    /// The bounds of the display without task bars and docks.
+    pub usable_bounds: sdl2::rect::Rect,
+    /// The diagonal, horizontal and vertical DPI, if known.
+    pub dpi: Option<(f32, f32, f32)>,
+    /// The current display mode.
+    pub current_mode: sdl2::video::DisplayMode,
+    /// The display mode of the desktop.
+    pub desktop_mode: sdl2::video::DisplayMode,
+    /// The available display modes, ordered from largest to smallest
+    /// and from highest to lowest refresh rate.
+    pub modes: Vec<sdl2::video::DisplayMode>,
+}
+
+/// SDL2 specific settings, used alongside `WindowSettings`
+/// when creating a window with `Sdl2Window::with_sdl2_settings`.
+#[derive(Clone, Debug)]
+pub struct Sdl2WindowSettings {
+    key_repeat: bool,
+    text_input: bool,
+    display: Option<i32>,
+    position: Option<Position>,
+    min_size: Option<Size>,
+    max_size: Option<Size>,
+    aspect_ratio: Option<f64>,
+    icon: Option<(u32, u32, Vec<u8>)>,
+    color_bits: [u8; 4],
+    depth_bits: Option<u8>,
+    stencil_bits: u8,
+    accum_bits: [u8; 4],
+    double_buffer: bool,
+    debug: bool,
+    forward_compatible: bool,
+    robust_access: bool,
+    release_behavior: ContextReleaseBehavior,
+    no_error: bool,
+    fallback_apis: Vec<Api>,
+}
+
+impl Sdl2WindowSettings {
+    /// Creates SDL2 settings with defaults.
+    ///
+    /// - key_repeat: false
+    /// - text_input: true
+    /// - display: None
+    /// - position: None
+    /// - min_size: None
+    /// - max_size: None
+    /// - aspect_ratio: None
+    /// - icon: None
+    /// - color_bits: [8, 8, 8, 8]
+    /// - depth_bits: None
+    /// - stencil_bits: 8
+    /// - accum_bits: [0, 0, 0, 0]
+    /// - double_buffer: true
+    /// - debug: false
+    /// - forward_compatible: false
+    /// - robust_access: false
+    /// - release_behavior: ContextReleaseBehavior::Flush
+    /// - no_error: false
+    /// - fallback_apis: []
+    ///
+    /// OpenGL attributes other than color, stencil and depth bits
+    /// are only set when they differ from the SDL defaults listed here,
+    /// so values set on the video subsystem beforehand are kept.
+    pub fn new() -> Sdl2WindowSettings {
+        Sdl2WindowSettings {
+            key_repeat: false,
+            text_input: true,
+            display: None,
+            position: None,
+            min_size: None,
+            max_size: None,
+            aspect_ratio: None,
+            icon: None,
+            color_bits: [8, 8, 8, 8],
+            depth_bits: None,
+            stencil_bits: 8,
+            accum_bits: [0, 0, 0, 0],
+            double_buffer: true,
+            debug: false,
+            forward_compatible: false,
+            robust_access: false,
+            release_behavior: ContextReleaseBehavior::Flush,
+            no_error: false,
+            fallback_apis: vec![],
+        }
+    }
+
+    /// Gets whether held keys emit repeated presses.
+    pub fn get_key_repeat(&self) -> bool {
+        self.key_repeat
+    }
+
+    /// Sets whether held keys emit repeated presses.
+    ///
+    /// See `Sdl2Window::set_key_repeat`.
+    pub fn set_key_repeat(&mut self, value: bool) {
+        self.key_repeat = value;
+    }
+
+    /// Sets whether held keys emit repeated presses.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_key_repeat()`](#method.set_key_repeat),
+    /// so that it can be used in method chaining.
+    pub fn key_repeat(mut self, value: bool) -> Self {
+        self.set_key_repeat(value);
+        self
+    }
+
+    /// Gets whether built windows start with text input enabled.
+    pub fn get_text_input(&self) -> bool {
+        self.text_input
+    }
+
+    /// Sets whether built windows start with text input enabled.
+    ///
+    /// See `Sdl2Window::start_text_input`.
+    pub fn set_text_input(&mut self, value: bool) {
+        self.text_input = value;
+    }
+
+    /// Sets whether built windows start with text input enabled.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_text_input()`](#method.set_text_input),
+    /// so that it can be used in method chaining.
+    pub fn text_input(mut self, value: bool) -> Self {
+        self.set_text_input(value);
+        self
+    }
+
+    /// Gets the index of the display built windows are placed on.
+    pub fn get_display(&self) -> Option<i32> {
+        self.display
+    }
+
+    /// Sets the index of the display built windows are placed on,
+    /// or `None` for the primary display.
+    ///
+    /// Fullscreen windows cover this display.
+    pub fn set_display(&mut self, value: Option<i32>) {
+        self.display = value;
+    }
+
+    /// Sets the index of the display built windows are placed on,
+    /// or `None` for the primary display.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_display()`](#method.set_display),
+    /// so that it can be used in method chaining.
+    pub fn display(mut self, value: Option<i32>) -> Self {
+        self.set_display(value);
+        self
+    }
+
+    /// Gets the position of built windows.
+    pub fn get_position(&self) -> Option<Position> {
+        self.position
+    }
+
+    /// Sets the position of built windows, or `None` to center them.
+    ///
+    /// The position is relative to the display when one is set,
+    /// otherwise it is in desktop coordinates.
+    pub fn set_position(&mut self, value: Option<Position>) {
+        self.position = value;
+    }
+
+    /// Sets the position of built windows, or `None` to center them.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_position()`](#method.set_position),
+    /// so that it can be used in method chaining.
+    pub fn position(mut self, value: Option<Position>) -> Self {
+        self.set_position(value);
+        self
+    }
+
+    /// Gets the minimum size of built windows.
+    pub fn get_min_size(&self) -> Option<Size> {
+        self.min_size
+    }
+
+    /// Sets the minimum size of built windows.
+    pub fn set_min_size(&mut self, value: Option<Size>) {
+        self.min_size = value;
+    }
+
+    /// Sets the minimum size of built windows.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_min_size()`](#method.set_min_size),
+    /// so that it can be used in method chaining.
+    pub fn min_size(mut self, value: Option<Size>) -> Self {
+        self.set_min_size(value);
+        self
+    }
+
+    /// Gets the maximum size of built windows.
+    pub fn get_max_size(&self) -> Option<Size> {
+        self.max_size
+    }
+
+    /// Sets the maximum size of built windows.
+    pub fn set_max_size(&mut self, value: Option<Size>) {
+        self.max_size = value;
+    }
+
+    /// Sets the maximum size of built windows.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_max_size()`](#method.set_max_size),
+    /// so that it can be used in method chaining.
+    pub fn max_size(mut self, value: Option<Size>) -> Self {
+        self.set_max_size(value);
+        self
+    }
+
+    /// Gets the aspect ratio built windows keep when resizing.
+    pub fn get_aspect_ratio(&self) -> Option<f64> {
+        self.aspect_ratio
+    }
+
+    /// Sets the aspect ratio built windows keep when resizing,
+    /// as width divided by height.
+    ///
+    /// Building a window fails if the ratio is not finite and greater than zero.
+    /// See `Sdl2Window::set_aspect_ratio`.
+    pub fn set_aspect_ratio(&mut self, value: Option<f64>) {
+        self.aspect_ratio = value;
+    }
+
+    /// Sets the aspect ratio built windows keep when resizing,
+    /// as width divided by height.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_aspect_ratio()`](#method.set_aspect_ratio),
+    /// so that it can be used in method chaining.
+    pub fn aspect_ratio(mut self, value: Option<f64>) -> Self {
+        self.set_aspect_ratio(value);
+        self
+    }
+
+    /// Gets the icon of built windows, as width, height and RGBA pixel data.
+    pub fn get_icon(&self) -> Option<&(u32, u32, Vec<u8>)> {
+        self.icon.as_ref()
+    }
+
+    /// Sets the icon of built windows, as width, height and RGBA pixel data
+    /// with 4 bytes per pixel row by row.
+    pub fn set_icon(&mut self, value: Option<(u32, u32, Vec<u8>)>) {
+        self.icon = value;
+    }
+
+    /// Sets the icon of built windows, as width, height and RGBA pixel data
+    /// with 4 bytes per pixel row by row.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_icon()`](#method.set_icon),
+    /// so that it can be used in method chaining.
+    pub fn icon(mut self, value: Option<(u32, u32, Vec<u8>)>) -> Self {
+        self.set_icon(value);
+        self
+    }
+
+    /// Gets the minimum number of bits for the red, green, blue and alpha channels
+    /// of the color buffer.
+    pub fn get_color_bits(&self) -> [u8; 4] {
+        self.color_bits
+    }
+
+    /// Sets the minimum number of bits for the red, green, blue and alpha channels
+    /// of the color buffer.
+    pub fn set_color_bits(&mut self, value: [u8; 4]) {
+        self.color_bits = value;
+    }
+
+    /// Sets the minimum number of bits for the red, green, blue and alpha channels
+    /// of the color buffer.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_color_bits()`](#method.set_color_bits),
+    /// so that it can be used in method chaining.
+    pub fn color_bits(mut self, value: [u8; 4]) -> Self {
+        self.set_color_bits(value);
+        self
+    }
+
+    /// Gets the minimum number of bits in the depth buffer.
+    pub fn get_depth_bits(&self) -> Option<u8> {
+        self.depth_bits
+    }
+
+    /// Sets the minimum number of bits in the depth buffer.
+    ///
+    /// With `None`, the driver default is used, which is usually 16 bits.
+    /// Window creation fails if the requested size is not available.
+    pub fn set_depth_bits(&mut self, value: Option<u8>) {
+        self.depth_bits = value;
+    }
+
+    /// Sets the minimum number of bits in the depth buffer.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_depth_bits()`](#method.set_depth_bits),
+    /// so that it can be used in method chaining.
+    pub fn depth_bits(mut self, value: Option<u8>) -> Self {
+        self.set_depth_bits(value);
+        self
+    }
+
+    /// Gets the minimum number of bits in the stencil buffer.
+    pub fn get_stencil_bits(&self) -> u8 {
+        self.stencil_bits
+    }
+
+    /// Sets the minimum number of bits in the stencil buffer.
+    pub fn set_stencil_bits(&mut self, value: u8) {
+        self.stencil_bits = value;
+    }
+
+    /// Sets the minimum number of bits in the stencil buffer.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_stencil_bits()`](#method.set_stencil_bits),
+    /// so that it can be used in method chaining.
+    pub fn stencil_bits(mut self, value: u8) -> Self {
+        self.set_stencil_bits(value);
+        self
+    }
+
+    /// Gets the minimum number of bits for the red, green, blue and alpha channels
+    /// of the accumulation buffer.
+    pub fn get_accum_bits(&self) -> [u8; 4] {
+        self.accum_bits
+    }
+
+    /// Sets the minimum number of bits for the red, green, blue and alpha channels
+    /// of the accumulation buffer.
+    pub fn set_accum_bits(&mut self, value: [u8; 4]) {
+        self.accum_bits = value;
+    }
+
+    /// Sets the minimum number of bits for the red, green, blue and alpha channels
+    /// of the accumulation buffer.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_accum_bits()`](#method.set_accum_bits),
+    /// so that it can be used in method chaining.
+    pub fn accum_bits(mut self, value: [u8; 4]) -> Self {
+        self.set_accum_bits(value);
+        self
+    }
+
+    /// Gets whether the color buffer is double buffered.
+    pub fn get_double_buffer(&self) -> bool {
+        self.double_buffer
+    }
+
+    /// Sets whether the color buffer is double buffered.
+    pub fn set_double_buffer(&mut self, value: bool) {
+        self.double_buffer = value;
+    }
+
+    /// Sets whether the color buffer is double buffered.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_double_buffer()`](#method.set_double_buffer),
+    /// so that it can be used in method chaining.
+    pub fn double_buffer(mut self, value: bool) -> Self {
+        self.set_double_buffer(value);
+        self
+    }
+
+    /// Gets whether a debug context is requested.
+    pub fn get_debug(&self) -> bool {
+        self.debug
+    }
+
+    /// Sets whether a debug context is requested.
+    ///
+    /// Debug contexts can report errors and performance warnings
+    /// through `glDebugMessageCallback`, at some cost of speed.
+    pub fn set_debug(&mut self, value: bool) {
+        self.debug = value;
+    }
+
+    /// Sets whether a debug context is requested.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_debug()`](#method.set_debug),
+    /// so that it can be used in method chaining.
+    pub fn debug(mut self, value: bool) -> Self {
+        self.set_debug(value);
+        self
+    }
+
+    /// Gets whether a forward compatible context is requested.
+    pub fn get_forward_compatible(&self) -> bool {
+        self.forward_compatible
+    }
+
+    /// Sets whether a forward compatible context is requested.
+    ///
+    /// Forward compatible contexts remove deprecated functionality.
+    pub fn set_forward_compatible(&mut self, value: bool) {
+        self.forward_compatible = value;
+    }
+
+    /// Sets whether a forward compatible context is requested.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_forward_compatible()`](#method.set_forward_compatible),
+    /// so that it can be used in method chaining.
+    pub fn forward_compatible(mut self, value: bool) -> Self {
+        self.set_forward_compatible(value);
+        self
+    }
+
+    /// Gets whether a context with robust buffer access is requested.
+    pub fn get_robust_access(&self) -> bool {
+        self.robust_access
+    }
+
+    /// Sets whether a context with robust buffer access is requested.
+    pub fn set_robust_access(&mut self, value: bool) {
+        self.robust_access = value;
+    }
+
+    /// Sets whether a context with robust buffer access is requested.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_robust_access()`](#method.set_robust_access),
+    /// so that it can be used in method chaining.
+    pub fn robust_access(mut self, value: bool) -> Self {
+        self.set_robust_access(value);
+        self
+    }
+
+    /// Gets what happens to pending commands when the context is released.
+    pub fn get_release_behavior(&self) -> ContextReleaseBehavior {
+        self.release_behavior
+    }
+
+    /// Sets what happens to pending commands when the context is released.
+    pub fn set_release_behavior(&mut self, value: ContextReleaseBehavior) {
+        self.release_behavior = value;
+    }
+
+    /// Sets what happens to pending commands when the context is released.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_release_behavior()`](#method.set_release_behavior),
+    /// so that it can be used in method chaining.
+    pub fn release_behavior(mut self, value: ContextReleaseBehavior) -> Self {
+        self.set_release_behavior(value);
+        self
+    }
+
+    /// Gets whether a context without error checking is requested.
+    pub fn get_no_error(&self) -> bool {
+        self.no_error
+    }
+
+    /// Sets whether a context without error checking is requested.
+    ///
+    /// Errors in such a context result in undefined behavior.
+    /// Requires SDL 2.0.6 or later.
+    pub fn set_no_error(&mut self, value: bool) {
+        self.no_error = value;
+    }
+
+    /// Sets whether a context without error checking is requested.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_no_error()`](#method.set_no_error),
+    /// so that it can be used in method chaining.
+    pub fn no_error(mut self, value: bool) -> Self {
+        self.set_no_error(value);
+        self
+    }
+
+    /// Gets the graphics APIs to try, in order, when the graphics API
+    /// of the window settings is unavailable.
+    pub fn get_fallback_apis(&self) -> &[Api] {
+        &self.fallback_apis
+    }
+
+    /// Sets the graphics APIs to try, in order, when the graphics API
+    /// of the window settings is unavailable.
+    ///
+    /// Use `Sdl2Window::get_graphics_api` to find out which one was obtained.
+    pub fn set_fallback_apis(&mut self, value: Vec<Api>) {
+        self.fallback_apis = value;
+    }
+
+    /// Sets the graphics APIs to try, in order, when the graphics API
+    /// of the window settings is unavailable.
+    ///
+    /// This method moves the current settings,
+    /// unlike [`set_fallback_apis()`](#method.set_fallback_apis),
+    /// so that it can be used in method chaining.
+    pub fn fallback_apis(mut self, value: Vec<Api>) -> Self {
+        self.set_fallback_apis(value);
+        self
+    }
+}
+
+impl Default for Sdl2WindowSettings {
+    fn default() -> Sdl2WindowSettings {
+        Sdl2WindowSettings::new()
     }
 }
 
@@ -53,12 +962,30 @@ pub struct Sdl2Window {
     automatic_close: bool,
     // Stores relative coordinates to emit on next poll.
     mouse_relative: Option<(f64, f64, TimeStamp)>,
+    // Stores events to emit on next polls, when one SDL event
+    // translates into several events.
+    pending_events: VecDeque<Event>,
     // Whether the cursor is captured.
     is_capturing_cursor: bool,
+    // Whether the cursor is captured using relative mouse mode,
+    // instead of warping the mouse.
+    is_relative_mouse_mode: bool,
     // Used to ignore relative events when warping mouse
     // to center of window.
     ignore_relative_event: Option<(i32, i32)>,
     exit_on_esc: bool,
+    // Width divided by height to keep when resizing.
+    aspect_ratio: Option<f64>,
+    // Window size of the last resize event.
+    last_size: (i32, i32),
+    // Graphics API of the created context.
+    api: Api,
+    // Whether to emit key repeat events.
+    key_repeat: bool,
+    // Keyboard modifiers as of the last key event.
+    keymod: sdl2::keyboard::Mod,
+    // The current cursor, released when replaced.
+    cursor: Option<sdl2::mouse::Cursor>,
     title: String,
 }
 
@@ -71,141 +998,699 @@ impl Sdl2Window {
         Ok(Self::with_subsystem(video_subsystem, settings)?)
     }
 
-    /// Creates a window with the supplied SDL Video subsystem.
-    pub fn with_subsystem(video_subsystem: sdl2::VideoSubsystem,
-                          settings: &WindowSettings)
-                          -> Result<Self, Box<dyn Error>> {
-        use sdl2::video::GLProfile;
+    /// Creates a window with the supplied SDL Video subsystem.
+    pub fn with_subsystem(video_subsystem: sdl2::VideoSubsystem,
+                          settings: &WindowSettings)
+                          -> Result<Self, Box<dyn Error>> {
+        Self::with_sdl2_settings(video_subsystem, settings, &Sdl2WindowSettings::new())
+    }
+
+    /// Creates a window with the supplied SDL Video subsystem,
+    /// using SDL2 specific settings in addition to the window settings.
+    ///
+    /// Transparent windows are only supported on X11 and Wayland with SDL 2.24 or later.
+    pub fn with_sdl2_settings(video_subsystem: sdl2::VideoSubsystem,
+                              settings: &WindowSettings,
+                              sdl2_settings: &Sdl2WindowSettings)
+                              -> Result<Self, Box<dyn Error>> {
+        use sdl2::video::GLProfile;
+
+        let sdl_context = video_subsystem.sdl();
+        let mut apis = vec![settings.get_maybe_graphics_api().unwrap_or(Api::opengl(3, 2))];
+        apis.extend(sdl2_settings.get_fallback_apis().iter().cloned());
+        for api in &apis {
+            if api.api != "OpenGL" && api.api != OPENGL_ES {
+                return Err(UnsupportedGraphicsApiError {
+                    found: api.api.clone(),
+                    expected: vec!["OpenGL".into(), OPENGL_ES.into()],
+                }.into());
+            }
+        }
+
+        {
+            let gl_attr = video_subsystem.gl_attr();
+
+            // Not all drivers default to 32bit color, so explicitly set it to 32bit color.
+            let [r, g, b, a] = sdl2_settings.get_color_bits();
+            gl_attr.set_red_size(r);
+            gl_attr.set_green_size(g);
+            gl_attr.set_blue_size(b);
+            gl_attr.set_alpha_size(a);
+            if let Some(bits) = sdl2_settings.get_depth_bits() {
+                gl_attr.set_depth_size(bits);
+            }
+            gl_attr.set_stencil_size(sdl2_settings.get_stencil_bits());
+            gl_attr.set_framebuffer_srgb_compatible(settings.get_srgb());
+
+            // Attributes at their SDL default are left alone,
+            // keeping any value set on the video subsystem by the caller.
+            let accum_bits = sdl2_settings.get_accum_bits();
+            if accum_bits != [0, 0, 0, 0] {
+                let [r, g, b, a] = accum_bits;
+                gl_attr.set_accum_red_size(r);
+                gl_attr.set_accum_green_size(g);
+                gl_attr.set_accum_blue_size(b);
+                gl_attr.set_accum_alpha_size(a);
+            }
+            if !sdl2_settings.get_double_buffer() {
+                gl_attr.set_double_buffer(false);
+            }
+            if sdl2_settings.get_debug() || sdl2_settings.get_forward_compatible() ||
+               sdl2_settings.get_robust_access() {
+                // Setting context flags replaces them, so keep the current ones.
+                let current = gl_attr.context_flags();
+                let mut flags = gl_attr.set_context_flags();
+                if current.has_debug() || sdl2_settings.get_debug() {
+                    flags.debug();
+                }
+                if current.has_forward_compatible() || sdl2_settings.get_forward_compatible() {
+                    flags.forward_compatible();
+                }
+                if current.has_robust_access() || sdl2_settings.get_robust_access() {
+                    flags.robust_access();
+                }
+                if current.has_reset_isolation() {
+                    flags.reset_isolation();
+                }
+                flags.set();
+            }
+            if sdl2_settings.get_no_error() {
+                gl_attr.set_context_no_error(true);
+            }
+        }
+
+        if sdl2_settings.get_release_behavior() == ContextReleaseBehavior::None {
+            use sdl2::sys::{SDL_GLattr, SDL_GLcontextReleaseFlag};
+
+            let res = unsafe {
+                sdl2::sys::SDL_GL_SetAttribute(
+                    SDL_GLattr::SDL_GL_CONTEXT_RELEASE_BEHAVIOR,
+                    SDL_GLcontextReleaseFlag::SDL_GL_CONTEXT_RELEASE_BEHAVIOR_NONE as i32)
+            };
+            if res != 0 {
+                return Err(sdl2::get_error().into());
+            }
+        }
+
+        // Hints only apply to this window, and are restored when it has been created.
+        let mut hints = HintGuard::new();
+        if settings.get_transparent() {
+            // Let the compositor blend the window using the alpha channel
+            // of the framebuffer. SDL supports this with EGL on X11 and Wayland,
+            // starting with SDL 2.24. Other platforms create an opaque window.
+            hints.set("SDL_VIDEO_X11_FORCE_EGL", "1");
+            hints.set("SDL_VIDEO_EGL_ALLOW_TRANSPARENCY", "1");
+        }
+        let mut window_builder = video_subsystem.window(&settings.get_title(),
+                                                        settings.get_size().width as u32,
+                                                        settings.get_size().height as u32);
+
+        let window_builder = match (sdl2_settings.get_display(), sdl2_settings.get_position()) {
+            (None, None) => window_builder.position_centered(),
+            (Some(display), None) => {
+                // Same as `SDL_WINDOWPOS_CENTERED_DISPLAY(display)`.
+                let centered = sdl2::sys::SDL_WINDOWPOS_CENTERED_MASK as i32 | display;
+                window_builder.position(centered, centered)
+            }
+            (None, Some(pos)) => window_builder.position(pos.x, pos.y),
+            (Some(display), Some(pos)) => {
+                let bounds = video_subsystem.display_bounds(display)?;
+                window_builder.position(bounds.x() + pos.x, bounds.y() + pos.y)
+            }
+        };
+
+        let window_builder = window_builder.opengl();
+
+        let window_builder = if settings.get_resizable() {
+            window_builder.resizable()
+        } else {
+            window_builder
+        };
+
+        let window_builder = if settings.get_decorated() {
+            window_builder
+        } else {
+            window_builder.borderless()
+        };
+
+        let window_builder = if settings.get_fullscreen() {
+            window_builder.fullscreen()
+        } else {
+            window_builder
+        };
+
+        // Try each graphics API in turn, keeping the first one
+        // for which both the window and the context can be created.
+        let default_profile = video_subsystem.gl_attr().context_profile();
+        let mut created = None;
+        let mut last_error = String::new();
+        for api in apis {
+            {
+                let gl_attr = video_subsystem.gl_attr();
+                gl_attr.set_context_version(api.major as u8, api.minor as u8);
+                if api.api == OPENGL_ES {
+                    gl_attr.set_context_profile(GLProfile::GLES);
+                } else if api >= Api::opengl(3, 2) {
+                    gl_attr.set_context_profile(GLProfile::Core);
+                } else {
+                    gl_attr.set_context_profile(default_profile);
+                }
+                if settings.get_samples() != 0 {
+                    gl_attr.set_multisample_buffers(1);
+                    gl_attr.set_multisample_samples(settings.get_samples());
+                }
+            }
+
+            let window = match window_builder.build() {
+                Ok(w) => w,
+                Err(_) if settings.get_samples() != 0 => {
+                    // Retry without requiring anti-aliasing.
+                    let gl_attr = video_subsystem.gl_attr();
+                    gl_attr.set_multisample_buffers(0);
+                    gl_attr.set_multisample_samples(0);
+                    match window_builder.build() {
+                        Ok(w) => w,
+                        Err(e) => {
+                            last_error = format!("{}", e);
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => {
+                    last_error = format!("{}", e);
+                    continue;
+                }
+            };
+
+            match window.gl_create_context() {
+                Ok(context) => {
+                    created = Some((api, window, context));
+                    break;
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        let (api, window, context) = match created {
+            Some(x) => x,
+            None => return Err(last_error.into()),
+        };
+        drop(hints);
+
+        // Send text input events.
+        // SDL starts text input on some platforms, so stop it explicitly.
+        if sdl2_settings.get_text_input() {
+            video_subsystem.text_input().start();
+        } else {
+            video_subsystem.text_input().stop();
+        }
+
+        // Load the OpenGL function pointers.
+        // OpenGL ES uses the same entry points as its desktop counterpart.
+        gl::load_with(|name| video_subsystem.gl_get_proc_address(name) as *const _);
+
+        if settings.get_vsync() {
+            video_subsystem.gl_set_swap_interval(1)?;
+        } else {
+            video_subsystem.gl_set_swap_interval(0)?;
+        }
+
+        let (w, h) = window.size();
+        let mut window = Sdl2Window {
+            exit_on_esc: settings.get_exit_on_esc(),
+            key_repeat: sdl2_settings.get_key_repeat(),
+            keymod: sdl2::keyboard::Mod::NOMOD,
+            cursor: None,
+            should_close: false,
+            automatic_close: settings.get_automatic_close(),
+            is_capturing_cursor: false,
+            is_relative_mouse_mode: false,
+            ignore_relative_event: None,
+            window: window,
+            context: context,
+            sdl_context: sdl_context,
+            video_subsystem: video_subsystem,
+            joystick_state: None,
+            mouse_relative: None,
+            pending_events: VecDeque::new(),
+            aspect_ratio: None,
+            last_size: (w as i32, h as i32),
+            api,
+            title: settings.get_title(),
+        };
+        if let Some(size) = sdl2_settings.get_min_size() {
+            window.set_min_size(size)?;
+        }
+        if let Some(size) = sdl2_settings.get_max_size() {
+            window.set_max_size(size)?;
+        }
+        window.set_aspect_ratio(sdl2_settings.get_aspect_ratio())?;
+        if let Some(&(w, h, ref rgba)) = sdl2_settings.get_icon() {
+            window.set_icon(w, h, rgba)?;
+        }
+        if settings.get_controllers() {
+            window.init_joysticks()?;
+        }
+        Ok(window)
+    }
+
+    /// Gets the graphics API of the OpenGL context.
+    ///
+    /// This is the graphics API of the window settings,
+    /// or one of the fallback APIs in `Sdl2WindowSettings` if it was unavailable.
+    pub fn get_graphics_api(&self) -> Api {
+        self.api.clone()
+    }
+
+    /// Gets whether held keys emit repeated presses.
+    pub fn get_key_repeat(&self) -> bool {
+        self.key_repeat
+    }
+
+    /// Sets whether held keys emit repeated presses.
+    ///
+    /// Repeated presses are emitted as custom events with id `event_id::KEY_REPEAT`
+    /// carrying `ButtonArgs`, so they are not mistaken for new key presses.
+    pub fn set_key_repeat(&mut self, value: bool) {
+        self.key_repeat = value;
+    }
+
+    /// Gets the keyboard modifier state as of the last key event,
+    /// including Caps Lock and Num Lock.
+    pub fn get_modifiers(&self) -> sdl2::keyboard::Mod {
+        self.keymod
+    }
+
+    /// Gets the Ctrl, Shift, Alt and Gui modifier state as of the last key event.
+    pub fn get_modifier_keys(&self) -> keyboard::ModifierKey {
+        sdl2_map_modifiers(self.keymod)
+    }
+
+    /// Initialize the joystick subsystem. Required before joystick input
+    /// events will be returned. Returns the number available or error.
+    pub fn init_joysticks(&mut self) -> Result<u32, String> {
+        let subsystem = self.sdl_context.joystick()?;
+        let mut state = JoystickState::new(subsystem);
+        state.haptic_subsystem = self.sdl_context.haptic().ok();
+
+        // Open all the joysticks
+        let available = state.open_all()?;
+
+        self.joystick_state = Some(state);
+
+        Ok(available)
+    }
+
+    /// Initialize the game controller subsystem. Devices with a known mapping
+    /// are opened as game controllers and report buttons and axes using the
+    /// identifiers in `controller_button` and `controller_axis`.
+    /// Other devices are opened as raw joysticks.
+    /// Returns the number available or error.
+    pub fn init_game_controllers(&mut self) -> Result<u32, String> {
+        let subsystem = self.sdl_context.joystick()?;
+        let mut state = JoystickState::new(subsystem);
+        state.controller_subsystem = Some(self.sdl_context.game_controller()?);
+        state.haptic_subsystem = self.sdl_context.haptic().ok();
+
+        // Open all the joysticks and game controllers
+        let available = state.open_all()?;
+
+        self.joystick_state = Some(state);
+
+        Ok(available)
+    }
+
+    /// Loads game controller mappings from a file in `gamecontrollerdb.txt` format.
+    /// Devices that got a mapping are reopened as game controllers.
+    /// Returns the number of mappings added or error.
+    pub fn load_controller_mappings<P: AsRef<Path>>(&mut self, path: P) -> Result<i32, String> {
+        let added = self.sdl_context.game_controller()?
+            .load_mappings(path)
+            .map_err(|e| format!("{}", e))?;
+        self.reopen_game_controllers()?;
+        Ok(added)
+    }
+
+    /// Adds a single game controller mapping in `gamecontrollerdb.txt` format.
+    /// Devices that got a mapping are reopened as game controllers.
+    pub fn add_controller_mapping(&mut self, mapping: &str) -> Result<(), String> {
+        self.sdl_context.game_controller()?
+            .add_mapping(mapping)
+            .map_err(|e| format!("{}", e))?;
+        self.reopen_game_controllers()
+    }
+
+    /// Returns `true` if the device with the instance id `id` is opened as a
+    /// game controller, `false` if it is a raw joystick or not opened.
+    pub fn is_game_controller(&self, id: u32) -> bool {
+        match self.joystick_state {
+            Some(ref state) => state.is_game_controller(id),
+            None => false,
+        }
+    }
+
+    /// Returns information about all opened controllers.
+    pub fn controllers(&self) -> Vec<ControllerInfo> {
+        match self.joystick_state {
+            Some(ref state) => state.joysticks.iter().map(|j| state.info(j)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns information about the controller with instance id `id`,
+    /// or `None` if it is not opened.
+    pub fn controller_info(&self, id: u32) -> Option<ControllerInfo> {
+        let state = self.joystick_state.as_ref()?;
+        state.joysticks.iter()
+            .find(|j| j.instance_id() == id)
+            .map(|j| state.info(j))
+    }
+
+    /// Returns the current position of a controller axis, normalized the same way
+    /// as in `ControllerAxisArgs`, or `None` if there is no such controller or axis.
+    pub fn controller_axis(&self, id: u32, axis: u8) -> Option<f64> {
+        self.joystick_state.as_ref()?.axis(id, axis)
+    }
+
+    /// Starts a rumble effect on the controller with instance id `id`.
+    /// The intensities of the low and high frequency motors range from 0 to 0xFFFF.
+    /// Calling this again replaces the current effect, zero intensities stop it.
+    ///
+    /// Devices without native rumble support fall back to the haptic subsystem.
+    /// Returns an error if the device does not support either.
+    pub fn rumble(&mut self, id: u32, low_freq: u16, high_freq: u16, duration: Duration)
+                  -> Result<(), String> {
+        match self.joystick_state {
+            Some(ref mut state) => state.rumble(id, low_freq, high_freq, duration_ms(duration)),
+            None => Err("Joysticks are not initialized".into()),
+        }
+    }
+
+    /// Starts a rumble effect in the triggers of the controller with instance id `id`.
+    /// The intensities range from 0 to 0xFFFF.
+    /// Calling this again replaces the current effect, zero intensities stop it.
+    ///
+    /// Returns an error if the device does not support trigger rumble.
+    pub fn rumble_triggers(&mut self, id: u32, left: u16, right: u16, duration: Duration)
+                           -> Result<(), String> {
+        match self.joystick_state {
+            Some(ref mut state) => state.rumble_triggers(id, left, right, duration_ms(duration)),
+            None => Err("Joysticks are not initialized".into()),
+        }
+    }
+
+    fn reopen_game_controllers(&mut self) -> Result<(), String> {
+        if let Some(ref mut state) = self.joystick_state {
+            if state.controller_subsystem.is_some() {
+                state.open_all()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the keyboard keys that are currently held down.
+    pub fn pressed_keys(&self) -> Vec<keyboard::Key> {
+        let event_pump = self.sdl_context.event_pump().unwrap();
+        event_pump.keyboard_state()
+            .pressed_scancodes()
+            .filter_map(sdl2::keyboard::Keycode::from_scancode)
+            .map(sdl2_map_key)
+            .collect()
+    }
+
+    /// Returns the scancodes of the keyboard keys that are currently held down.
+    pub fn pressed_scancodes(&self) -> Vec<i32> {
+        let event_pump = self.sdl_context.event_pump().unwrap();
+        event_pump.keyboard_state()
+            .pressed_scancodes()
+            .map(|scode| scode as i32)
+            .collect()
+    }
+
+    /// Returns the current mouse cursor position in window coordinates.
+    pub fn mouse_position(&self) -> [f64; 2] {
+        let s = self.sdl_context.event_pump().unwrap().mouse_state();
+        [s.x() as f64, s.y() as f64]
+    }
+
+    /// Returns the mouse buttons that are currently held down.
+    pub fn pressed_mouse_buttons(&self) -> Vec<MouseButton> {
+        let s = self.sdl_context.event_pump().unwrap().mouse_state();
+        s.pressed_mouse_buttons().map(sdl2_map_mouse).collect()
+    }
+
+    /// Sets the window icon from RGBA pixel data,
+    /// with 4 bytes per pixel row by row.
+    pub fn set_icon(&mut self, width: u32, height: u32, rgba: &[u8]) -> Result<(), String> {
+        let surface = rgba_surface(width, height, rgba)?;
+        self.window.set_icon(surface);
+        Ok(())
+    }
+
+    /// Gets the opacity of the whole window, from 0.0 to 1.0.
+    pub fn get_opacity(&self) -> Result<f32, String> {
+        self.window.opacity()
+    }
+
+    /// Sets the opacity of the whole window, from 0.0 to 1.0.
+    ///
+    /// For per-pixel transparency, build the window with
+    /// `WindowSettings::transparent` instead.
+    pub fn set_opacity(&mut self, value: f32) -> Result<(), String> {
+        self.window.set_opacity(value)
+    }
+
+    /// Gets the minimum size of the window.
+    pub fn get_min_size(&self) -> Size {
+        let (w, h) = self.window.minimum_size();
+        Size {width: w as f64, height: h as f64}
+    }
+
+    /// Sets the minimum size the window can be resized to.
+    pub fn set_min_size<S: Into<Size>>(&mut self, size: S) -> Result<(), String> {
+        let size: Size = size.into();
+        self.window.set_minimum_size(size.width as u32, size.height as u32)
+            .map_err(|e| format!("{}", e))
+    }
+
+    /// Gets the maximum size of the window, where zero means unconstrained.
+    pub fn get_max_size(&self) -> Size {
+        let (w, h) = self.window.maximum_size();
+        Size {width: w as f64, height: h as f64}
+    }
+
+    /// Sets the maximum size the window can be resized to.
+    pub fn set_max_size<S: Into<Size>>(&mut self, size: S) -> Result<(), String> {
+        let size: Size = size.into();
+        self.window.set_maximum_size(size.width as u32, size.height as u32)
+            .map_err(|e| format!("{}", e))
+    }
+
+    /// Gets the aspect ratio kept when resizing, as width divided by height.
+    pub fn get_aspect_ratio(&self) -> Option<f64> {
+        self.aspect_ratio
+    }
+
+    /// Sets the aspect ratio to keep when resizing, as width divided by height,
+    /// or `None` to resize freely.
+    ///
+    /// Resizes are corrected before emitting the resize event.
+    /// The current size is corrected right away, keeping the width.
+    /// Fullscreen and maximized windows are not corrected.
+    ///
+    /// Returns an error if the ratio is not finite and greater than zero.
+    pub fn set_aspect_ratio(&mut self, value: Option<f64>) -> Result<(), String> {
+        if let Some(ratio) = value {
+            if !(ratio.is_finite() && ratio > 0.0) {
+                return Err(format!("Invalid aspect ratio: {}", ratio));
+            }
+        }
+        self.aspect_ratio = value;
+        if let (Some(ratio), false) = (value, self.is_size_managed()) {
+            let (w, h) = self.window.size();
+            self.last_size = (w as i32, h as i32);
+            self.last_size = self.correct_aspect_ratio(w as i32, h as i32, ratio);
+        }
+        Ok(())
+    }
+
+    /// Returns information about all displays.
+    pub fn displays(&self) -> Result<Vec<DisplayInfo>, String> {
+        sdl2_displays(&self.video_subsystem)
+    }
 
-        let sdl_context = video_subsystem.sdl();
-        let api = settings.get_maybe_graphics_api().unwrap_or(Api::opengl(3, 2));
-        if api.api != "OpenGL" {
-            return Err(UnsupportedGraphicsApiError {
-                found: api.api,
-                expected: vec!["OpenGL".into()],
-            }.into());
-        }
+    /// Gets the index of the display the window is on.
+    pub fn get_display_index(&self) -> Result<i32, String> {
+        self.window.display_index()
+    }
 
-        {
-            let gl_attr = video_subsystem.gl_attr();
+    /// Gets the fullscreen mode of the window.
+    pub fn get_fullscreen_mode(&self) -> Result<FullscreenMode, String> {
+        use sdl2::video::FullscreenType;
 
-            // Not all drivers default to 32bit color, so explicitly set it to 32bit color.
-            gl_attr.set_red_size(8);
-            gl_attr.set_green_size(8);
-            gl_attr.set_blue_size(8);
-            gl_attr.set_alpha_size(8);
-            gl_attr.set_stencil_size(8);
-            gl_attr.set_context_version(api.major as u8, api.minor as u8);
-            gl_attr.set_framebuffer_srgb_compatible(settings.get_srgb());
-        }
+        Ok(match self.window.fullscreen_state() {
+            FullscreenType::Off => FullscreenMode::Windowed,
+            FullscreenType::Desktop => FullscreenMode::Desktop,
+            FullscreenType::True => FullscreenMode::Exclusive(Some(self.window.display_mode()?)),
+        })
+    }
 
-        if api >= Api::opengl(3, 2) {
-            video_subsystem.gl_attr().set_context_profile(GLProfile::Core);
+    /// Sets the fullscreen mode of the window, keeping the OpenGL context.
+    ///
+    /// If the new size is known right away, a resize event is emitted afterwards.
+    /// Otherwise, for example when the window manager applies fullscreen later,
+    /// the resize event is emitted once SDL reports the new size.
+    pub fn set_fullscreen_mode(&mut self, mode: FullscreenMode) -> Result<(), String> {
+        use sdl2::video::FullscreenType;
+
+        match mode {
+            FullscreenMode::Windowed => self.window.set_fullscreen(FullscreenType::Off)?,
+            FullscreenMode::Desktop => self.window.set_fullscreen(FullscreenType::Desktop)?,
+            FullscreenMode::Exclusive(display_mode) => {
+                self.window.set_display_mode(display_mode)?;
+                self.window.set_fullscreen(FullscreenType::True)?;
+            }
         }
-        if settings.get_samples() != 0 {
-            let gl_attr = video_subsystem.gl_attr();
-            gl_attr.set_multisample_buffers(1);
-            gl_attr.set_multisample_samples(settings.get_samples());
+        let window_size = self.size();
+        let size = (window_size.width as i32, window_size.height as i32);
+        if size != self.last_size {
+            let draw_size = self.draw_size();
+            self.last_size = size;
+            self.pending_events.push_back(input::Event::Input(Input::Resize(ResizeArgs {
+                window_size: window_size.into(),
+                draw_size: draw_size.into(),
+            }), None));
         }
+        Ok(())
+    }
 
-        let mut window_builder = video_subsystem.window(&settings.get_title(),
-                                                        settings.get_size().width as u32,
-                                                        settings.get_size().height as u32);
+    /// Gets whether the mouse is confined to the window.
+    pub fn get_mouse_grab(&self) -> bool {
+        self.window.mouse_grab()
+    }
 
-        let window_builder = window_builder.position_centered()
-            .opengl();
+    /// Sets whether the mouse is confined to the window.
+    ///
+    /// Unlike capturing the cursor, the cursor stays visible
+    /// and emits normal cursor motion.
+    pub fn set_mouse_grab(&mut self, value: bool) {
+        self.window.set_mouse_grab(value);
+    }
 
-        let window_builder = if settings.get_resizable() {
-            window_builder.resizable()
-        } else {
-            window_builder
-        };
+    /// Gets whether the keyboard is grabbed by the window.
+    pub fn get_keyboard_grab(&self) -> bool {
+        self.window.keyboard_grab()
+    }
 
-        let window_builder = if settings.get_decorated() {
-            window_builder
-        } else {
-            window_builder.borderless()
-        };
+    /// Sets whether the keyboard is grabbed by the window.
+    ///
+    /// When grabbed, system shortcuts such as Alt+Tab are sent to the window
+    /// instead, on platforms that support it.
+    pub fn set_keyboard_grab(&mut self, value: bool) {
+        self.window.set_keyboard_grab(value);
+    }
 
-        let window_builder = if settings.get_fullscreen() {
-            window_builder.fullscreen()
-        } else {
-            window_builder
-        };
+    /// Gets the rectangle the mouse is confined to, in window coordinates.
+    pub fn get_mouse_rect(&self) -> Option<sdl2::rect::Rect> {
+        self.window.mouse_rect()
+    }
 
-        let window = window_builder.build();
+    /// Sets the rectangle the mouse is confined to, in window coordinates,
+    /// or `None` to remove the confinement.
+    ///
+    /// The mouse is only confined while the window has focus.
+    pub fn set_mouse_rect(&mut self, rect: Option<sdl2::rect::Rect>) -> Result<(), String> {
+        self.window.set_mouse_rect(rect)
+    }
 
-        let window = match window {
-            Ok(w) => w,
-            Err(_) => {
-                if settings.get_samples() != 0 {
-                    // Retry without requiring anti-aliasing.
-                    let gl_attr = video_subsystem.gl_attr();
-                    gl_attr.set_multisample_buffers(0);
-                    gl_attr.set_multisample_samples(0);
-                    window_builder.build().map_err(|e| format!("{}", e))?
-                } else {
-                    window.map_err(|e| format!("{}", e))?
-                }
-            }
-        };
+    /// Sets the cursor to a system cursor shape.
+    pub fn set_system_cursor(&mut self, cursor: sdl2::mouse::SystemCursor) -> Result<(), String> {
+        let cursor = sdl2::mouse::Cursor::from_system(cursor)?;
+        cursor.set();
+        self.cursor = Some(cursor);
+        Ok(())
+    }
 
-        // Send text input events.
-        video_subsystem.text_input().start();
+    /// Sets the cursor to a custom image from RGBA pixel data,
+    /// with 4 bytes per pixel row by row.
+    /// The hot spot is the position in the image that points at the mouse position.
+    pub fn set_custom_cursor(&mut self,
+                             width: u32,
+                             height: u32,
+                             rgba: &[u8],
+                             hot_x: i32,
+                             hot_y: i32)
+                             -> Result<(), String> {
+        let surface = rgba_surface(width, height, rgba)?;
+        let cursor = sdl2::mouse::Cursor::from_surface(surface, hot_x, hot_y)?;
+        cursor.set();
+        self.cursor = Some(cursor);
+        Ok(())
+    }
 
-        let context = window.gl_create_context()
-            .map_err(|e| format!("{}", e))?;
+    /// Resets the cursor to the default cursor.
+    pub fn reset_cursor(&mut self) {
+        // SDL switches to the default cursor when freeing the current one.
+        self.cursor = None;
+    }
 
-        // Load the OpenGL function pointers.
-        gl::load_with(|name| video_subsystem.gl_get_proc_address(name) as *const _);
+    /// Gets the text in the clipboard.
+    pub fn get_clipboard_text(&self) -> Result<String, String> {
+        self.video_subsystem.clipboard().clipboard_text()
+    }
 
-        if settings.get_vsync() {
-            video_subsystem.gl_set_swap_interval(1)?;
-        } else {
-            video_subsystem.gl_set_swap_interval(0)?;
+    /// Sets the text in the clipboard.
+    pub fn set_clipboard_text(&mut self, text: &str) -> Result<(), String> {
+        if text.contains('\0') {
+            return Err("Clipboard text can not contain nul characters".into());
         }
+        self.video_subsystem.clipboard().set_clipboard_text(text)
+    }
 
-        let mut window = Sdl2Window {
-            exit_on_esc: settings.get_exit_on_esc(),
-            should_close: false,
-            automatic_close: settings.get_automatic_close(),
-            is_capturing_cursor: false,
-            ignore_relative_event: None,
-            window: window,
-            context: context,
-            sdl_context: sdl_context,
-            video_subsystem: video_subsystem,
-            joystick_state: None,
-            mouse_relative: None,
-            title: settings.get_title(),
-        };
-        if settings.get_controllers() {
-            window.init_joysticks()?;
-        }
-        if settings.get_transparent() {
-            let _ = window.window.set_opacity(0.0);
-        }
-        Ok(window)
+    /// Returns `true` if the clipboard contains non-empty text.
+    pub fn has_clipboard_text(&self) -> bool {
+        self.video_subsystem.clipboard().has_clipboard_text()
     }
 
-    /// Initialize the joystick subsystem. Required before joystick input
-    /// events will be returned. Returns the number available or error.
-    pub fn init_joysticks(&mut self) -> Result<u32, String> {
-        let subsystem = self.sdl_context.joystick().map_err(|e| format!("{}", e))?;
-        let mut state = JoystickState::new(subsystem);
-        let available = state.subsystem.num_joysticks().map_err(|e| format!("{}", e))?;
+    /// Gets the text in the primary selection,
+    /// on platforms that have one such as X11 and Wayland.
+    pub fn get_primary_selection_text(&self) -> Result<String, String> {
+        self.video_subsystem.clipboard().primary_selection_text()
+    }
 
-        // Open all the joysticks
-        for id in 0..available {
-            match state.subsystem.open(id) {
-                Ok(c) => state.joysticks.push(c),
-                Err(e) => return Err(format!("{}", e)),
-            }
+    /// Sets the text in the primary selection,
+    /// on platforms that have one such as X11 and Wayland.
+    pub fn set_primary_selection_text(&mut self, text: &str) -> Result<(), String> {
+        if text.contains('\0') {
+            return Err("Primary selection text can not contain nul characters".into());
         }
+        self.video_subsystem.clipboard().set_primary_selection_text(text)
+    }
 
-        self.joystick_state = Some(state);
+    /// Returns `true` if the primary selection contains non-empty text.
+    pub fn has_primary_selection_text(&self) -> bool {
+        self.video_subsystem.clipboard().has_primary_selection_text()
+    }
 
-        Ok(available)
+    /// Starts sending text input and text editing events.
+    /// Shows the on-screen keyboard or IME on platforms that have one.
+    pub fn start_text_input(&mut self) {
+        self.video_subsystem.text_input().start();
+    }
+
+    /// Stops sending text input and text editing events.
+    pub fn stop_text_input(&mut self) {
+        self.video_subsystem.text_input().stop();
+    }
+
+    /// Returns `true` if text input and text editing events are sent.
+    pub fn is_text_input_active(&self) -> bool {
+        self.video_subsystem.text_input().is_active()
+    }
+
+    /// Sets the rectangle in window coordinates where text is being edited,
+    /// used to place the IME candidate list.
+    pub fn set_text_input_rect(&mut self, rect: sdl2::rect::Rect) {
+        self.video_subsystem.text_input().set_rect(rect);
     }
 
     fn wait_event(&mut self) -> Event {
@@ -263,7 +1748,7 @@ impl Sdl2Window {
             return Some(input::Event::Input(
                 Input::Move(Motion::MouseRelative([x, y])), Some(timestamp)));
         }
-        None
+        self.pending_events.pop_front()
     }
 
     fn handle_event(&mut self,
@@ -287,7 +1772,7 @@ impl Sdl2Window {
             None => {
                 // Wait until event queue is empty to reduce
                 // risk of error in order.
-                if self.is_capturing_cursor {
+                if self.is_capturing_cursor && !self.is_relative_mouse_mode {
                     self.fake_capture();
                 }
                 return None;
@@ -303,26 +1788,50 @@ impl Sdl2Window {
             Event::TextInput { text, timestamp, .. } => {
                 return Some(input::Event::Input(Input::Text(text), Some(timestamp)));
             }
-            Event::KeyDown { keycode: Some(key), repeat, scancode, timestamp, .. } => {
-                // SDL2 repeats the key down event.
-                // If the event is the same as last one, ignore it.
+            Event::TextEditing { text, start, length, timestamp, .. } => {
+                return Some(input::Event::Custom(event_id::TEXT_EDITING, Arc::new(TextEditingArgs {
+                    text,
+                    cursor: start,
+                    selection_length: length,
+                }), Some(timestamp)));
+            }
+            Event::KeyDown { keycode: Some(key), repeat, scancode, keymod, timestamp, .. } => {
+                self.keymod = keymod;
+                // SDL2 repeats the key down event while the key is held.
+                // Repeats are ignored unless enabled.
                 if repeat {
-                    return self.poll_event();
+                    if self.key_repeat &&
+                       !(self.exit_on_esc && key == sdl2::keyboard::Keycode::Escape) {
+                        return Some(input::Event::Custom(event_id::KEY_REPEAT,
+                            Arc::new(ButtonArgs {
+                                state: ButtonState::Press,
+                                button: Button::Keyboard(sdl2_map_key(key)),
+                                scancode: scancode.map(|scode| scode as i32),
+                            }), Some(timestamp)));
+                    }
+                    *unknown = true;
+                    return None;
                 }
 
                 if self.exit_on_esc && key == sdl2::keyboard::Keycode::Escape {
+                    // Emit a close event instead of the key press,
+                    // the same way as when closing the window.
                     self.should_close = true;
-                } else {
-                    return Some(input::Event::Input(Input::Button(ButtonArgs {
-                        state: ButtonState::Press,
-                        button: Button::Keyboard(sdl2_map_key(key)),
-                        scancode: scancode.map(|scode| scode as i32),
-                    }), Some(timestamp)));
+                    return Some(input::Event::Input(Input::Close(CloseArgs), Some(timestamp)));
                 }
+                return Some(input::Event::Input(Input::Button(ButtonArgs {
+                    state: ButtonState::Press,
+                    button: Button::Keyboard(sdl2_map_key(key)),
+                    scancode: scancode.map(|scode| scode as i32),
+                }), Some(timestamp)));
             }
-            Event::KeyUp { keycode: Some(key), repeat, scancode, timestamp, .. } => {
-                if repeat {
-                    return self.poll_event();
+            Event::KeyUp { keycode: Some(key), repeat, scancode, keymod, timestamp, .. } => {
+                self.keymod = keymod;
+                // The Esc key press is not emitted when exiting on Esc,
+                // so the release is not emitted either.
+                if repeat || (self.exit_on_esc && key == sdl2::keyboard::Keycode::Escape) {
+                    *unknown = true;
+                    return None;
                 }
                 return Some(input::Event::Input(Input::Button(ButtonArgs {
                     state: ButtonState::Release,
@@ -361,6 +1870,14 @@ impl Sdl2Window {
                 return Some(input::Event::Input(
                     Input::Move(Motion::MouseScroll([x as f64, y as f64])), Some(timestamp)));
             }
+            Event::JoyAxisMotion { which, .. } |
+            Event::JoyButtonDown { which, .. } |
+            Event::JoyButtonUp { which, .. } |
+            Event::JoyHatMotion { which, .. } if self.is_game_controller(which) => {
+                // Reported through the game controller events instead.
+                *unknown = true;
+                return None;
+            }
             Event::JoyAxisMotion { which, axis_idx, value: val, timestamp, .. } => {
                 // Axis motion is an absolute value in the range
                 // [-32768, 32767]. Normalize it down to a float.
@@ -396,9 +1913,92 @@ impl Sdl2Window {
                 HatState::LeftUp => PistonHat::LeftUp,
                 HatState::LeftDown => PistonHat::LeftDown,
               };
-              return Some(input::Event::Input(Input::Button(ButtonArgs {
+              let previous = match self.joystick_state {
+                  Some(ref mut joystick_state) => joystick_state.hats
+                      .insert((which, hat_idx), state)
+                      .unwrap_or(PistonHat::Centered),
+                  None => PistonHat::Centered,
+              };
+              let hat_event = |button_state, hat_state| {
+                  input::Event::Input(Input::Button(ButtonArgs {
+                      state: button_state,
+                      button: Button::Hat(ControllerHat::new(which, hat_idx, hat_state)),
+                      scancode: None,
+                  }), Some(timestamp))
+              };
+              let (release, press) = hat_transition(previous, state);
+              let release = release.map(|hat| hat_event(ButtonState::Release, hat));
+              let press = press.map(|hat| hat_event(ButtonState::Press, hat));
+              match (release, press) {
+                  (Some(release), Some(press)) => {
+                      self.pending_events.push_back(press);
+                      return Some(release);
+                  }
+                  (Some(event), None) | (None, Some(event)) => return Some(event),
+                  (None, None) => {
+                      *unknown = true;
+                      return None;
+                  }
+              }
+            }
+            Event::JoyDeviceAdded { which, timestamp, .. } => {
+                // Devices available at initialization are also reported here,
+                // but they are already opened.
+                let opened = match self.joystick_state {
+                    Some(ref mut state) => state.open(which),
+                    None => Ok(None),
+                };
+                match opened {
+                    Ok(Some(id)) => {
+                        return Some(input::Event::Custom(event_id::CONTROLLER_DEVICE,
+                            Arc::new(ControllerDeviceArgs { id, connected: true }),
+                            Some(timestamp)));
+                    }
+                    Ok(None) => {
+                        *unknown = true;
+                        return None;
+                    }
+                    Err(error) => {
+                        return Some(input::Event::Custom(event_id::CONTROLLER_ERROR,
+                            Arc::new(ControllerErrorArgs { device_index: which, error }),
+                            Some(timestamp)));
+                    }
+                }
+            }
+            Event::JoyDeviceRemoved { which, timestamp, .. } => {
+                let closed = match self.joystick_state {
+                    Some(ref mut state) => state.close(which),
+                    None => false,
+                };
+                if closed {
+                    return Some(input::Event::Custom(event_id::CONTROLLER_DEVICE,
+                        Arc::new(ControllerDeviceArgs { id: which, connected: false }),
+                        Some(timestamp)));
+                }
+                *unknown = true;
+                return None;
+            }
+            Event::ControllerAxisMotion { which, axis, value: val, timestamp, .. } => {
+                // Axis motion is an absolute value in the range
+                // [-32768, 32767]. Normalize it down to a float.
+                let normalized_value = val as f64 / i16::MAX as f64;
+                return Some(input::Event::Input(Input::Move(
+                    Motion::ControllerAxis(ControllerAxisArgs::new(
+                    which, sdl2_map_controller_axis(axis), normalized_value))), Some(timestamp)));
+            }
+            Event::ControllerButtonDown { which, button, timestamp, .. } => {
+                return Some(input::Event::Input(Input::Button(ButtonArgs {
+                    state: ButtonState::Press,
+                    button: Button::Controller(ControllerButton::new(
+                        which, sdl2_map_controller_button(button))),
+                    scancode: None,
+                }), Some(timestamp)))
+            }
+            Event::ControllerButtonUp { which, button, timestamp, .. } => {
+                return Some(input::Event::Input(Input::Button(ButtonArgs {
                     state: ButtonState::Release,
-                    button: Button::Hat(ControllerHat::new(which, hat_idx, state)),
+                    button: Button::Controller(ControllerButton::new(
+                        which, sdl2_map_controller_button(button))),
                     scancode: None,
                 }), Some(timestamp)))
             }
@@ -427,13 +2027,28 @@ impl Sdl2Window {
                              Some(timestamp)))
             }
             Event::Window { win_event: sdl2::event::WindowEvent::Resized(w, h), timestamp, .. } => {
-                let draw_size = self.draw_size();
+                let mut draw_size = self.draw_size();
+                let (w, h) = match self.aspect_ratio {
+                    Some(ratio) if !self.is_size_managed() => {
+                        let (new_w, new_h) = self.correct_aspect_ratio(w, h, ratio);
+                        if (new_w, new_h) != (w, h) && w > 0 && h > 0 {
+                            // The draw size is not updated yet, so scale it.
+                            draw_size.width *= new_w as f64 / w as f64;
+                            draw_size.height *= new_h as f64 / h as f64;
+                        }
+                        (new_w, new_h)
+                    }
+                    _ => (w, h),
+                };
+                self.last_size = (w, h);
                 return Some(input::Event::Input(Input::Resize(ResizeArgs {
                     window_size: [w as f64, h as f64],
                     draw_size: draw_size.into(),
                 }), Some(timestamp)));
             }
             Event::Window { win_event: WindowEvent::FocusGained, timestamp, .. } => {
+                // Modifiers might have changed while the window did not have focus.
+                self.keymod = self.sdl_context.keyboard().mod_state();
                 return Some(input::Event::Input(Input::Focus(true), Some(timestamp)));
             }
             Event::Window { win_event: WindowEvent::FocusLost, timestamp, .. } => {
@@ -445,14 +2060,90 @@ impl Sdl2Window {
             Event::Window { win_event: WindowEvent::Leave, timestamp, .. } => {
                 return Some(input::Event::Input(Input::Cursor(false), Some(timestamp)));
             }
-            _ => {
+            Event::Window { win_event, timestamp, .. } => {
+                if let Some(args) = sdl2_map_window_event(win_event) {
+                    return Some(input::Event::Custom(event_id::WINDOW, Arc::new(args),
+                                                     Some(timestamp)));
+                }
                 *unknown = true;
                 return None;
             }
+            Event::ClipboardUpdate { timestamp, .. } => {
+                return Some(input::Event::Custom(event_id::CLIPBOARD_UPDATE,
+                                                 Arc::new(()), Some(timestamp)));
+            }
+            Event::DropBegin { timestamp, .. } => {
+                return Some(input::Event::Custom(event_id::DROP, Arc::new(DropArgs {
+                    state: DropState::Begin,
+                    position: self.drop_position(),
+                }), Some(timestamp)));
+            }
+            Event::DropFile { filename, timestamp, .. } => {
+                let path = PathBuf::from(filename);
+                self.pending_events.push_back(input::Event::Custom(event_id::DROP,
+                    Arc::new(DropArgs {
+                        state: DropState::File(path.clone()),
+                        position: self.drop_position(),
+                    }), Some(timestamp)));
+                return Some(input::Event::Input(
+                    Input::FileDrag(FileDrag::Drop(path)), Some(timestamp)));
+            }
+            Event::DropText { filename: text, timestamp, .. } => {
+                return Some(input::Event::Custom(event_id::DROP, Arc::new(DropArgs {
+                    state: DropState::Text(text),
+                    position: self.drop_position(),
+                }), Some(timestamp)));
+            }
+            Event::DropComplete { timestamp, .. } => {
+                return Some(input::Event::Custom(event_id::DROP, Arc::new(DropArgs {
+                    state: DropState::Complete,
+                    position: self.drop_position(),
+                }), Some(timestamp)));
+            }
+            _ => {
+                *unknown = true;
+            }
         }
         None
     }
 
+    // Whether the window size is decided by the system, because the window
+    // is fullscreen or maximized. Such sizes are not corrected to the aspect ratio,
+    // since setting the size would either not take effect or change the display mode.
+    fn is_size_managed(&self) -> bool {
+        use sdl2::video::FullscreenType;
+
+        self.window.fullscreen_state() != FullscreenType::Off || self.window.is_maximized()
+    }
+
+    // Corrects a window size to an aspect ratio and resizes the window if needed.
+    fn correct_aspect_ratio(&mut self, w: i32, h: i32, ratio: f64) -> (i32, i32) {
+        let (new_w, new_h) = aspect_corrected_size(w, h, self.last_size, ratio,
+                                                   self.window.minimum_size(),
+                                                   self.window.maximum_size());
+        if (new_w, new_h) != (w, h) {
+            let _ = self.window.set_size(new_w.max(1) as u32, new_h.max(1) as u32);
+        }
+        (new_w, new_h)
+    }
+
+    fn drop_position(&self) -> Option<[f64; 2]> {
+        // SDL does not report where items are dropped, and the window usually
+        // does not have mouse focus while dragging, so use the global mouse position.
+        let (mut x, mut y) = (0, 0);
+        unsafe {
+            sdl2::sys::SDL_GetGlobalMouseState(&mut x, &mut y);
+        }
+        let (window_x, window_y) = self.window.position();
+        let (w, h) = self.window.size();
+        let (x, y) = (x - window_x, y - window_y);
+        if x >= 0 && y >= 0 && (x as u32) < w && (y as u32) < h {
+            Some([x as f64, y as f64])
+        } else {
+            None
+        }
+    }
+
     fn fake_capture(&mut self) {
         // Fake capturing of cursor.
         let (w, h) = self.window.size();
@@ -477,6 +2168,9 @@ impl BuildFromWindowSettings for Sdl2Window {
 impl Drop for Sdl2Window {
     fn drop(&mut self) {
         self.set_capture_cursor(false);
+        self.set_mouse_grab(false);
+        self.set_keyboard_grab(false);
+        let _ = self.set_mouse_rect(None);
     }
 }
 
@@ -530,12 +2224,18 @@ impl AdvancedWindow for Sdl2Window {
         self.exit_on_esc = value;
     }
     fn set_capture_cursor(&mut self, value: bool) {
-        // Normally it should call `.set_relative_mouse_mode(value)`,
-        // but since it does not emit relative mouse events,
-        // we have to fake it by hiding the cursor and warping it
+        // Use relative mouse mode to get raw mouse motion.
+        // Where it is unavailable, fake it by hiding the cursor and warping it
         // back to the center of the window.
+        let mouse = self.sdl_context.mouse();
+        mouse.set_relative_mouse_mode(value);
+        self.is_relative_mouse_mode = value && mouse.relative_mouse_mode();
         self.is_capturing_cursor = value;
-        self.sdl_context.mouse().show_cursor(!value);
+        self.ignore_relative_event = None;
+        if self.is_relative_mouse_mode {
+            return;
+        }
+        mouse.show_cursor(!value);
         if value {
             // Move cursor to center of window now,
             // to get right relative mouse motion to ignore.
@@ -561,6 +2261,9 @@ impl AdvancedWindow for Sdl2Window {
     fn set_size<S: Into<Size>>(&mut self, size: S) {
         let size: Size = size.into();
         let _ = self.window.set_size(size.width as u32, size.height as u32);
+        // SDL does not emit resize events for sizes set by the application.
+        let (w, h) = self.window.size();
+        self.last_size = (w as i32, h as i32);
     }
 }
 
@@ -578,6 +2281,140 @@ impl OpenGLWindow for Sdl2Window {
     }
 }
 
+/// Returns information about all displays.
+pub fn sdl2_displays(video_subsystem: &sdl2::VideoSubsystem) -> Result<Vec<DisplayInfo>, String> {
+    let mut displays = Vec::new();
+    for index in 0..video_subsystem.num_video_displays()? {
+        let mut modes = Vec::new();
+        for mode_index in 0..video_subsystem.num_display_modes(index)? {
+            modes.push(video_subsystem.display_mode(index, mode_index)?);
+        }
+        displays.push(DisplayInfo {
+            index,
+            name: video_subsystem.display_name(index)?,
+            bounds: video_subsystem.display_bounds(index)?,
+            usable_bounds: video_subsystem.display_usable_bounds(index)?,
+            dpi: video_subsystem.display_dpi(index).ok(),
+            current_mode: video_subsystem.current_display_mode(index)?,
+            desktop_mode: video_subsystem.desktop_display_mode(index)?,
+            modes,
+        });
+    }
+    Ok(displays)
+}
+
+// Sets SDL hints, restoring their previous values when dropped.
+struct HintGuard {
+    previous: Vec<(&'static str, Option<String>)>,
+}
+
+impl HintGuard {
+    fn new() -> Self {
+        HintGuard { previous: Vec::new() }
+    }
+
+    fn set(&mut self, name: &'static str, value: &str) {
+        self.previous.push((name, sdl2::hint::get(name)));
+        sdl2::hint::set(name, value);
+    }
+}
+
+impl Drop for HintGuard {
+    fn drop(&mut self) {
+        for (name, value) in self.previous.drain(..).rev() {
+            match value {
+                Some(value) => {
+                    sdl2::hint::set(name, &value);
+                }
+                None => {
+                    // Clearing the value makes SDL fall back to the environment variable.
+                    let name = std::ffi::CString::new(name).unwrap();
+                    unsafe {
+                        sdl2::sys::SDL_SetHint(name.as_ptr(), std::ptr::null());
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Creates a surface from RGBA pixel data.
+fn rgba_surface(width: u32, height: u32, rgba: &[u8])
+                -> Result<sdl2::surface::Surface<'static>, String> {
+    use sdl2::pixels::PixelFormatEnum;
+
+    if width == 0 || height == 0 {
+        return Err("Expected non-zero image size".into());
+    }
+    let row = width as usize * 4;
+    if rgba.len() != row * height as usize {
+        return Err(format!("Expected {} bytes of RGBA data for {}x{} pixels, found {}",
+                           row * height as usize, width, height, rgba.len()));
+    }
+    let mut surface = sdl2::surface::Surface::new(width, height, PixelFormatEnum::RGBA32)?;
+    let pitch = surface.pitch() as usize;
+    surface.with_lock_mut(|pixels| {
+        for (y, src) in rgba.chunks(row).enumerate() {
+            pixels[y * pitch..y * pitch + row].copy_from_slice(src);
+        }
+    });
+    Ok(surface)
+}
+
+// Returns the hat direction to release and the hat direction to press
+// when a hat moves from `previous` to `state`.
+// The previous direction is released before pressing the new one,
+// and centered is never pressed.
+fn hat_transition(previous: PistonHat, state: PistonHat)
+                  -> (Option<PistonHat>, Option<PistonHat>) {
+    if previous == state {
+        return (None, None);
+    }
+    let release = if previous != PistonHat::Centered { Some(previous) } else { None };
+    let press = if state != PistonHat::Centered { Some(state) } else { None };
+    (release, press)
+}
+
+// Corrects a window size to an aspect ratio, keeping the dimension
+// that changed the most since the last size, within the size constraints.
+// A zero maximum means unconstrained.
+fn aspect_corrected_size(w: i32,
+                         h: i32,
+                         last: (i32, i32),
+                         ratio: f64,
+                         min: (u32, u32),
+                         max: (u32, u32))
+                         -> (i32, i32) {
+    let (last_w, last_h) = last;
+    let (min_w, min_h) = min;
+    let (max_w, max_h) = max;
+    let (mut new_w, mut new_h) = if (w - last_w).abs() >= (h - last_h).abs() {
+        (w, (w as f64 / ratio).round() as i32)
+    } else {
+        ((h as f64 * ratio).round() as i32, h)
+    };
+    if new_w < min_w as i32 || new_h < min_h as i32 {
+        new_w = new_w.max(min_w as i32).max((min_h as f64 * ratio).round() as i32);
+        new_h = (new_w as f64 / ratio).round() as i32;
+    }
+    if (max_w != 0 && new_w > max_w as i32) || (max_h != 0 && new_h > max_h as i32) {
+        if max_w != 0 {
+            new_w = new_w.min(max_w as i32);
+        }
+        if max_h != 0 {
+            new_w = new_w.min((max_h as f64 * ratio).round() as i32);
+        }
+        new_h = (new_w as f64 / ratio).round() as i32;
+    }
+    (new_w, new_h)
+}
+
+// Converts a duration to milliseconds as used by SDL, saturating on overflow.
+fn duration_ms(duration: Duration) -> u32 {
+    use std::convert::TryInto;
+    duration.as_millis().try_into().unwrap_or(u32::MAX)
+}
+
 /// Maps a SDL2 key to piston-input key.
 pub fn sdl2_map_key(keycode: sdl2::keyboard::Keycode) -> keyboard::Key {
     let keycode = keycode.into_i32();
@@ -588,6 +2425,50 @@ pub fn sdl2_map_key(keycode: sdl2::keyboard::Keycode) -> keyboard::Key {
     keycode.into()
 }
 
+/// Maps SDL2 keyboard modifiers to piston-input modifier keys.
+pub fn sdl2_map_modifiers(keymod: sdl2::keyboard::Mod) -> keyboard::ModifierKey {
+    use sdl2::keyboard::Mod;
+    use keyboard::ModifierKey;
+
+    let mut modifiers = ModifierKey::NO_MODIFIER;
+    if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+        modifiers.insert(ModifierKey::CTRL);
+    }
+    if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+        modifiers.insert(ModifierKey::SHIFT);
+    }
+    if keymod.intersects(Mod::LALTMOD | Mod::RALTMOD) {
+        modifiers.insert(ModifierKey::ALT);
+    }
+    if keymod.intersects(Mod::LGUIMOD | Mod::RGUIMOD) {
+        modifiers.insert(ModifierKey::GUI);
+    }
+    modifiers
+}
+
+/// Maps a SDL2 window event to window event arguments,
+/// or `None` for window events emitted as piston-input events or not supported.
+pub fn sdl2_map_window_event(win_event: sdl2::event::WindowEvent) -> Option<WindowEventArgs> {
+    use sdl2::event::WindowEvent as WE;
+
+    Some(match win_event {
+        WE::Shown => WindowEventArgs::Shown,
+        WE::Hidden => WindowEventArgs::Hidden,
+        WE::Exposed => WindowEventArgs::Exposed,
+        WE::Moved(x, y) => WindowEventArgs::Moved(Position { x, y }),
+        WE::SizeChanged(w, h) => WindowEventArgs::SizeChanged(Size {
+            width: w as f64,
+            height: h as f64,
+        }),
+        WE::Minimized => WindowEventArgs::Minimized,
+        WE::Maximized => WindowEventArgs::Maximized,
+        WE::Restored => WindowEventArgs::Restored,
+        WE::Close => WindowEventArgs::Close,
+        WE::TakeFocus => WindowEventArgs::TakeFocus,
+        _ => return None,
+    })
+}
+
 /// Maps a SDL2 mouse button to piston-input button.
 pub fn sdl2_map_mouse(button: sdl2::mouse::MouseButton) -> MouseButton {
     use sdl2::mouse::MouseButton as MB;
@@ -601,3 +2482,181 @@ pub fn sdl2_map_mouse(button: sdl2::mouse::MouseButton) -> MouseButton {
         MB::Unknown => MouseButton::Unknown,
     }
 }
+
+/// Maps a SDL2 game controller button to an identifier in `controller_button`.
+pub fn sdl2_map_controller_button(button: sdl2::controller::Button) -> u8 {
+    use sdl2::controller::Button as CB;
+
+    match button {
+        CB::A => controller_button::A,
+        CB::B => controller_button::B,
+        CB::X => controller_button::X,
+        CB::Y => controller_button::Y,
+        CB::Back => controller_button::BACK,
+        CB::Guide => controller_button::GUIDE,
+        CB::Start => controller_button::START,
+        CB::LeftStick => controller_button::LEFT_STICK,
+        CB::RightStick => controller_button::RIGHT_STICK,
+        CB::LeftShoulder => controller_button::LEFT_SHOULDER,
+        CB::RightShoulder => controller_button::RIGHT_SHOULDER,
+        CB::DPadUp => controller_button::DPAD_UP,
+        CB::DPadDown => controller_button::DPAD_DOWN,
+        CB::DPadLeft => controller_button::DPAD_LEFT,
+        CB::DPadRight => controller_button::DPAD_RIGHT,
+        CB::Misc1 => controller_button::MISC1,
+        CB::Paddle1 => controller_button::PADDLE1,
+        CB::Paddle2 => controller_button::PADDLE2,
+        CB::Paddle3 => controller_button::PADDLE3,
+        CB::Paddle4 => controller_button::PADDLE4,
+        CB::Touchpad => controller_button::TOUCHPAD,
+    }
+}
+
+/// Maps a SDL2 game controller axis to an identifier in `controller_axis`.
+pub fn sdl2_map_controller_axis(axis: sdl2::controller::Axis) -> u8 {
+    use sdl2::controller::Axis as CA;
+
+    match axis {
+        CA::LeftX => controller_axis::LEFT_X,
+        CA::LeftY => controller_axis::LEFT_Y,
+        CA::RightX => controller_axis::RIGHT_X,
+        CA::RightY => controller_axis::RIGHT_Y,
+        CA::TriggerLeft => controller_axis::TRIGGER_LEFT,
+        CA::TriggerRight => controller_axis::TRIGGER_RIGHT,
+    }
+}
+
+/// Button identifiers reported by devices opened as game controllers.
+///
+/// These follow the SDL2 game controller layout (Xbox style face buttons),
+/// so they are the same for every device with a mapping.
+pub mod controller_button {
+    /// Bottom face button.
+    pub const A: u8 = 0;
+    /// Right face button.
+    pub const B: u8 = 1;
+    /// Left face button.
+    pub const X: u8 = 2;
+    /// Top face button.
+    pub const Y: u8 = 3;
+    /// Back/select button.
+    pub const BACK: u8 = 4;
+    /// Guide/home button.
+    pub const GUIDE: u8 = 5;
+    /// Start button.
+    pub const START: u8 = 6;
+    /// Pressing the left stick.
+    pub const LEFT_STICK: u8 = 7;
+    /// Pressing the right stick.
+    pub const RIGHT_STICK: u8 = 8;
+    /// Left shoulder button.
+    pub const LEFT_SHOULDER: u8 = 9;
+    /// Right shoulder button.
+    pub const RIGHT_SHOULDER: u8 = 10;
+    /// D-pad up.
+    pub const DPAD_UP: u8 = 11;
+    /// D-pad down.
+    pub const DPAD_DOWN: u8 = 12;
+    /// D-pad left.
+    pub const DPAD_LEFT: u8 = 13;
+    /// D-pad right.
+    pub const DPAD_RIGHT: u8 = 14;
+    /// Extra button, e.g. share or capture button.
+    pub const MISC1: u8 = 15;
+    /// Upper or primary paddle, under the right hand.
+    pub const PADDLE1: u8 = 16;
+    /// Upper or primary paddle, under the left hand.
+    pub const PADDLE2: u8 = 17;
+    /// Lower or secondary paddle, under the right hand.
+    pub const PADDLE3: u8 = 18;
+    /// Lower or secondary paddle, under the left hand.
+    pub const PADDLE4: u8 = 19;
+    /// Touchpad button.
+    pub const TOUCHPAD: u8 = 20;
+}
+
+/// Axis identifiers reported by devices opened as game controllers.
+///
+/// Sticks are in the range [-1.0, 1.0], triggers in the range [0.0, 1.0].
+pub mod controller_axis {
+    /// Left stick horizontal axis.
+    pub const LEFT_X: u8 = 0;
+    /// Left stick vertical axis.
+    pub const LEFT_Y: u8 = 1;
+    /// Right stick horizontal axis.
+    pub const RIGHT_X: u8 = 2;
+    /// Right stick vertical axis.
+    pub const RIGHT_Y: u8 = 3;
+    /// Left trigger.
+    pub const TRIGGER_LEFT: u8 = 4;
+    /// Right trigger.
+    pub const TRIGGER_RIGHT: u8 = 5;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{aspect_corrected_size, hat_transition};
+    use input::HatState;
+
+    const RATIO: f64 = 4.0 / 3.0;
+
+    #[test]
+    fn aspect_keeps_width_when_width_changed_most() {
+        assert_eq!(aspect_corrected_size(900, 610, (800, 600), RATIO, (0, 0), (0, 0)),
+                   (900, 675));
+    }
+
+    #[test]
+    fn aspect_keeps_height_when_height_changed_most() {
+        assert_eq!(aspect_corrected_size(805, 700, (800, 600), RATIO, (0, 0), (0, 0)),
+                   (933, 700));
+    }
+
+    #[test]
+    fn aspect_unchanged_when_already_correct() {
+        assert_eq!(aspect_corrected_size(1024, 768, (800, 600), RATIO, (0, 0), (0, 0)),
+                   (1024, 768));
+    }
+
+    #[test]
+    fn aspect_clamps_to_min_size() {
+        assert_eq!(aspect_corrected_size(300, 200, (800, 600), RATIO, (400, 300), (0, 0)),
+                   (400, 300));
+        // The minimum height decides the width.
+        assert_eq!(aspect_corrected_size(300, 200, (800, 600), RATIO, (0, 450), (0, 0)),
+                   (600, 450));
+    }
+
+    #[test]
+    fn aspect_clamps_to_max_size() {
+        assert_eq!(aspect_corrected_size(1200, 900, (800, 600), RATIO, (0, 0), (1000, 0)),
+                   (1000, 750));
+        // The maximum height decides the width.
+        assert_eq!(aspect_corrected_size(1200, 900, (800, 600), RATIO, (0, 0), (0, 600)),
+                   (800, 600));
+    }
+
+    #[test]
+    fn hat_centering_only_releases() {
+        assert_eq!(hat_transition(HatState::Up, HatState::Centered),
+                   (Some(HatState::Up), None));
+    }
+
+    #[test]
+    fn hat_leaving_center_only_presses() {
+        assert_eq!(hat_transition(HatState::Centered, HatState::Up),
+                   (None, Some(HatState::Up)));
+    }
+
+    #[test]
+    fn hat_changing_direction_releases_then_presses() {
+        assert_eq!(hat_transition(HatState::Up, HatState::Right),
+                   (Some(HatState::Up), Some(HatState::Right)));
+    }
+
+    #[test]
+    fn hat_unchanged_does_nothing() {
+        assert_eq!(hat_transition(HatState::Up, HatState::Up), (None, None));
+        assert_eq!(hat_transition(HatState::Centered, HatState::Centered), (None, None));
+    }
+}